@@ -0,0 +1,230 @@
+//! Github App authentication.
+//!
+//! Webhook handlers frequently need to call back into the REST API in response to the event they
+//! just received (posting a check run status, a comment, a label, ...). Doing that as a Github
+//! App requires signing a short-lived RS256 JWT with the app's private key and exchanging it for
+//! an installation access token. [`AppAuth`] does both, and caches the installation tokens it
+//! obtains until shortly before they expire.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_std::sync::Mutex;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+
+/// How long before expiry a cached installation token is considered stale and refreshed.
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+
+/// How far back the JWT's `iat` claim is backdated, to tolerate clock skew with Github.
+const CLOCK_SKEW_MARGIN: Duration = Duration::from_secs(60);
+
+/// The maximum lifetime Github allows for a Github App JWT.
+const MAX_JWT_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+/// [`AppAuth`] generates and caches the credentials a Github App needs to call the REST API: a
+/// JWT identifying the app, and, per installation, a short-lived installation access token
+/// obtained by exchanging that JWT.
+pub struct AppAuth {
+    app_id: u64,
+    key: EncodingKey,
+    tokens: Mutex<HashMap<u64, CachedToken>>,
+}
+
+struct CachedToken {
+    token: String,
+    expires_at: SystemTime,
+}
+
+/// The claims of the JWT a Github App authenticates with.
+#[derive(Serialize)]
+struct Claims {
+    iat: u64,
+    exp: u64,
+    iss: String,
+}
+
+#[derive(Deserialize)]
+struct AccessTokenResponse {
+    token: String,
+    expires_at: String,
+}
+
+impl AppAuth {
+    /// Creates a new [`AppAuth`] for the Github App identified by `app_id`, authenticating with
+    /// the given PEM-encoded RSA private key.
+    pub fn new(app_id: u64, private_key: impl AsRef<[u8]>) -> Result<Self, Error> {
+        let key = EncodingKey::from_rsa_pem(private_key.as_ref())?;
+        Ok(AppAuth {
+            app_id,
+            key,
+            tokens: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Generates a fresh, short-lived JWT identifying the Github App.
+    fn jwt(&self) -> Result<String, Error> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock is set before the unix epoch");
+        let iat = now.saturating_sub(CLOCK_SKEW_MARGIN);
+        let exp = iat + MAX_JWT_LIFETIME;
+
+        let claims = Claims {
+            iat: iat.as_secs(),
+            exp: exp.as_secs(),
+            iss: self.app_id.to_string(),
+        };
+
+        Ok(jsonwebtoken::encode(
+            &Header::new(Algorithm::RS256),
+            &claims,
+            &self.key,
+        )?)
+    }
+
+    /// Returns a valid installation access token for `installation_id`, exchanging the app's JWT
+    /// for a new one if none is cached yet or the cached one is close to expiry.
+    pub async fn installation_token(&self, installation_id: u64) -> Result<String, Error> {
+        if let Some(cached) = self.tokens.lock().await.get(&installation_id) {
+            if cached.expires_at > SystemTime::now() + TOKEN_REFRESH_MARGIN {
+                return Ok(cached.token.clone());
+            }
+        }
+
+        let app_client = octocrab::OctocrabBuilder::new()
+            .personal_token(self.jwt()?)
+            .build()?;
+        let response: AccessTokenResponse = app_client
+            .post(
+                format!("/app/installations/{}/access_tokens", installation_id),
+                None::<&()>,
+            )
+            .await?;
+        let expires_at = parse_rfc3339(&response.expires_at).ok_or(Error::InvalidAccessTokenResponse)?;
+
+        self.tokens.lock().await.insert(
+            installation_id,
+            CachedToken {
+                token: response.token.clone(),
+                expires_at,
+            },
+        );
+
+        Ok(response.token)
+    }
+
+    /// Builds an [`octocrab::Octocrab`] client authenticated as the given installation, ready to
+    /// call the REST API back.
+    pub async fn client_for_installation(&self, installation_id: u64) -> Result<octocrab::Octocrab, Error> {
+        let token = self.installation_token(installation_id).await?;
+        Ok(octocrab::OctocrabBuilder::new().personal_token(token).build()?)
+    }
+}
+
+/// Parses the `YYYY-MM-DDTHH:MM:SSZ` timestamps Github's REST API returns, without pulling in a
+/// full date/time crate just for this.
+fn parse_rfc3339(s: &str) -> Option<SystemTime> {
+    let s = s.strip_suffix('Z')?;
+    let (date, time) = s.split_once('T')?;
+
+    let mut date = date.split('-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: u32 = date.next()?.parse().ok()?;
+    let day: u32 = date.next()?.parse().ok()?;
+
+    let mut time = time.split(':');
+    let hour: u64 = time.next()?.parse().ok()?;
+    let minute: u64 = time.next()?.parse().ok()?;
+    let second: u64 = time.next()?.parse().ok()?;
+
+    let days = days_from_civil(year, month, day);
+    if days < 0 {
+        return None;
+    }
+
+    let secs = days as u64 * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Converts a Gregorian calendar date into a count of days since the unix epoch.
+///
+/// Adapted from Howard Hinnant's well-known `days_from_civil` algorithm.
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let mp = (m as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rfc3339_timestamps() {
+        let cases = [
+            ("1970-01-01T00:00:00Z", 0),
+            ("1970-01-01T00:00:01Z", 1),
+            ("2023-06-15T12:34:56Z", 1_686_832_496),
+            ("2000-03-01T00:00:00Z", 951_868_800),
+        ];
+
+        for (input, expected_secs) in cases {
+            let parsed = parse_rfc3339(input).unwrap_or_else(|| panic!("failed to parse {}", input));
+            assert_eq!(
+                parsed.duration_since(UNIX_EPOCH).unwrap().as_secs(),
+                expected_secs,
+                "mismatch for {}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        let cases = [
+            "",
+            "2023-06-15T12:34:56",     // missing trailing `Z`
+            "2023-06-15 12:34:56Z",    // missing `T` separator
+            "2023-06-15T12:34Z",      // missing seconds
+            "not-a-date",
+        ];
+
+        for input in cases {
+            assert!(parse_rfc3339(input).is_none(), "unexpectedly parsed {}", input);
+        }
+    }
+
+    #[test]
+    fn days_from_civil_matches_known_reference_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1970, 1, 2), 1);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(2023, 6, 15), 19_523);
+    }
+
+    #[test]
+    fn new_rejects_an_invalid_private_key() {
+        let err = AppAuth::new(1, b"not a pem-encoded key").unwrap_err();
+        assert!(matches!(err, Error::Jwt(_)));
+    }
+}
+
+/// The errors that can occur while authenticating as a Github App.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    /// The given private key could not be parsed, or a JWT could not be signed with it.
+    #[error("Failed to sign the Github App JWT: {0}")]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+    /// Exchanging the JWT for an installation access token failed.
+    #[error("Failed to obtain an installation access token: {0}")]
+    Octocrab(#[from] octocrab::Error),
+    /// Github returned an access token response we could not make sense of.
+    #[error("Github returned an access token response we could not parse")]
+    InvalidAccessTokenResponse,
+}
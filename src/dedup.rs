@@ -0,0 +1,99 @@
+//! Delivery de-duplication.
+//!
+//! Github delivers webhooks at-least-once: network hiccups or timeouts on our end can cause the
+//! same event (identified by its `X-Github-Delivery` GUID) to be redelivered. [`DeliverySeen`] is
+//! a pluggable idempotency check the dispatcher consults before calling a handler, so a
+//! redelivery is detected and the handler is skipped instead of running its side-effects twice.
+
+use async_std::sync::Mutex;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// A store that remembers which webhook deliveries have already been handled.
+///
+/// Implement this to back the idempotency check with your own database; [`InMemoryDeliverySeen`]
+/// is provided as a default, process-local implementation.
+#[tide::utils::async_trait]
+pub trait DeliverySeen: Send + Sync {
+    /// Records `delivery_id` as seen, returning `true` if it had already been recorded before
+    /// (in which case the event is a redelivery and should be skipped).
+    async fn check_and_record(&self, delivery_id: &str) -> bool;
+}
+
+/// A default, in-memory [`DeliverySeen`] that remembers delivery ids for a configurable TTL,
+/// evicting expired entries lazily as new ones come in.
+///
+/// Being in-memory and process-local, this does not protect against redeliveries across restarts
+/// or across multiple server instances; back [`DeliverySeen`] with a shared database for that.
+pub struct InMemoryDeliverySeen {
+    seen: Mutex<HashMap<String, Instant>>,
+    ttl: Duration,
+}
+
+impl InMemoryDeliverySeen {
+    /// How long a delivery id is remembered by default.
+    pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+
+    /// Creates a store that remembers delivery ids for the given TTL.
+    pub fn new(ttl: Duration) -> Self {
+        InMemoryDeliverySeen {
+            seen: Mutex::new(HashMap::new()),
+            ttl,
+        }
+    }
+}
+
+impl Default for InMemoryDeliverySeen {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_TTL)
+    }
+}
+
+#[tide::utils::async_trait]
+impl DeliverySeen for InMemoryDeliverySeen {
+    async fn check_and_record(&self, delivery_id: &str) -> bool {
+        let mut seen = self.seen.lock().await;
+
+        let now = Instant::now();
+        seen.retain(|_, recorded_at| now.duration_since(*recorded_at) < self.ttl);
+
+        if seen.contains_key(delivery_id) {
+            true
+        } else {
+            seen.insert(delivery_id.to_owned(), now);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[async_std::test]
+    async fn second_delivery_of_the_same_id_is_reported_as_a_duplicate() {
+        let store = InMemoryDeliverySeen::new(Duration::from_secs(60));
+
+        assert!(!store.check_and_record("abc").await, "first delivery");
+        assert!(store.check_and_record("abc").await, "redelivery");
+    }
+
+    #[async_std::test]
+    async fn distinct_delivery_ids_are_not_considered_duplicates() {
+        let store = InMemoryDeliverySeen::new(Duration::from_secs(60));
+
+        assert!(!store.check_and_record("abc").await);
+        assert!(!store.check_and_record("xyz").await);
+    }
+
+    #[async_std::test]
+    async fn entries_are_evicted_once_their_ttl_elapses() {
+        let store = InMemoryDeliverySeen::new(Duration::from_millis(20));
+
+        assert!(!store.check_and_record("abc").await);
+        async_std::task::sleep(Duration::from_millis(50)).await;
+
+        // The id has aged out, so it's treated as a fresh delivery again.
+        assert!(!store.check_and_record("abc").await);
+    }
+}
@@ -9,8 +9,10 @@
 //! async fn main() -> tide::Result<()> {
 //!     let mut app = tide::new();
 //!     let github = tide_github::new(b"My Github webhook s3cr#t")
-//!         .on(Event::IssueComment, |payload| {
-//!             println!("Received a payload for repository {}", payload.repository.name);
+//!         .on(Event::IssueComment, |payload, _ctx| {
+//!             if let WebhookPayload::IssueComment(payload) = payload {
+//!                 println!("Received a payload for repository {}", payload.repository.name);
+//!             }
 //!         })
 //!         .build();
 //!     app.at("/gh_webhooks").nest(github);
@@ -23,11 +25,21 @@
 //! The API is still in development and may change in unexpected ways.
 use async_trait::async_trait;
 use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
 use tide::{prelude::*, Request, StatusCode};
 use std::sync::Arc;
 
+mod app_auth;
+mod dedup;
 mod middleware;
 mod payload;
+pub use app_auth::Error as AppAuthError;
+pub use dedup::{DeliverySeen, InMemoryDeliverySeen};
+pub use payload::{
+    CheckRunPayload, CheckSuitePayload, DeploymentPayload, IssueCommentPayload, IssuesPayload,
+    PingPayload, PullRequestPayload, PushPayload, ReleasePayload, WebhookPayload,
+};
 use payload::Payload;
 
 /// Returns a [`ServerBuilder`] with the given webhook secret.
@@ -35,36 +47,102 @@ use payload::Payload;
 /// Call [`Self::on()`](on@ServerBuilder) to register closures to be run when the given event is
 /// received and [`Self::build()`](build@ServerBuilder) to retrieve the final [`tide::Server`].
 pub fn new<S: Into<String>>(webhook_secret: S) -> ServerBuilder {
-    ServerBuilder::new(webhook_secret.into())
+    ServerBuilder::new(vec![webhook_secret.into()])
 }
 
-type HandlerMap = HashMap<
-    Event,
-    // TODO: Create a nice type alias for the Event Handler
-    Arc<dyn Send + Sync + 'static + Fn(Payload)>,
->;
+/// A boxed, pinned future as returned by an async event handler.
+type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// An event handler is either a blocking closure, run via `task::spawn_blocking`, or an async
+/// closure returning a future that is driven on the tide/async-std executor via `task::spawn`.
+enum Handler {
+    Blocking(Arc<dyn Send + Sync + 'static + Fn(WebhookPayload, Context)>),
+    Async(Arc<dyn Send + Sync + 'static + Fn(WebhookPayload, Context) -> BoxFuture>),
+}
+
+type HandlerMap = HashMap<Event, Handler>;
+
+/// [`Context`] carries information about the incoming webhook request alongside the
+/// [`WebhookPayload`] itself, since none of it is part of the payload's JSON body.
+pub struct Context {
+    /// The `X-Github-Delivery` GUID Github sent with this request, uniquely identifying this
+    /// delivery attempt. `None` if the header was missing.
+    pub delivery_id: Option<String>,
+    /// The raw `X-Github-Event` header value, e.g. `"issue_comment"`.
+    pub event_name: String,
+    /// The [`Event`] the `event_name` was parsed into.
+    pub event: Event,
+    /// The id of the webhook configuration that sent this request, from the `X-Github-Hook-ID`
+    /// header. `None` if the header was missing.
+    pub hook_id: Option<u64>,
+    /// An [`octocrab::Octocrab`] client authenticated as the installation the webhook came from,
+    /// ready to call the REST API back (e.g. to create a check run in response to a `check_suite`
+    /// webhook). `None` unless app authentication is configured and the payload included an
+    /// `installation` id.
+    pub client: Option<octocrab::Octocrab>,
+}
 
 /// [`ServerBuilder`] is used to first register closures to events before finally building a
 /// [`tide::Server`] using those closures.
 pub struct ServerBuilder {
-    webhook_secret: String,
+    webhook_secrets: Vec<String>,
     handlers: HandlerMap,
+    app_auth: Option<Arc<app_auth::AppAuth>>,
+    delivery_seen: Option<Arc<dyn DeliverySeen>>,
 }
 
 impl ServerBuilder {
-    fn new(webhook_secret: String) -> Self {
+    fn new(webhook_secrets: Vec<String>) -> Self {
         ServerBuilder {
-            webhook_secret,
+            webhook_secrets,
             handlers: HashMap::new(),
+            app_auth: None,
+            delivery_seen: None,
         }
     }
 
+    /// Registers an additional webhook secret: a request is accepted if its signature matches
+    /// *any* configured secret.
+    ///
+    /// This is primarily useful for zero-downtime secret rotation (configure both the old and the
+    /// new secret, wait for Github to be updated, then drop the old one) and for serving several
+    /// Github apps/orgs with different secrets through the one endpoint.
+    pub fn with_secret<S: Into<String>>(mut self, webhook_secret: S) -> Self {
+        self.webhook_secrets.push(webhook_secret.into());
+        self
+    }
+
+    /// Configures Github App authentication, given the app's id and PEM-encoded RSA private key.
+    ///
+    /// Once configured, a handler receives a [`Context`] whose `client` is an
+    /// [`octocrab::Octocrab`] client pre-authenticated as the installation the incoming webhook
+    /// came from, whenever the payload includes an `installation` id and obtaining the
+    /// installation token succeeds (otherwise `client` is `None`), so handlers can call back into the REST API (post a comment, create a check
+    /// run, ...) without managing JWTs or installation tokens themselves.
+    pub fn with_app_auth(mut self, app_id: u64, private_key: impl AsRef<[u8]>) -> Result<Self, AppAuthError> {
+        self.app_auth = Some(Arc::new(app_auth::AppAuth::new(app_id, private_key)?));
+        Ok(self)
+    }
+
+    /// Enables delivery de-duplication, backed by the given [`DeliverySeen`] store.
+    ///
+    /// Github redelivers webhooks at-least-once, so the same `X-Github-Delivery` id can arrive
+    /// more than once. When this is configured, the dispatcher consults the store before calling
+    /// a handler and skips the call if the delivery id was already recorded, preventing duplicate
+    /// side-effects. Use [`InMemoryDeliverySeen`] for a process-local default, or implement
+    /// [`DeliverySeen`] yourself to back it with a shared database.
+    pub fn with_delivery_dedup(mut self, store: Arc<dyn DeliverySeen>) -> Self {
+        self.delivery_seen = Some(store);
+        self
+    }
+
     /// Registers the given event handler to be run when the given event is received.
     ///
-    /// The event handler receives a [`Payload`] as the single argument. Since webhooks are
-    /// generally passively consumed (Github will not meaningfully (to us) process our response),
-    /// the handler returns only a `()`. As far as the event dispatcher is concerned, all the
-    /// meaningful work will be done as side-effects of the closures you register here.
+    /// The event handler receives a [`WebhookPayload`] and a [`Context`] as arguments. Since
+    /// webhooks are generally passively consumed (Github will not meaningfully (to us) process
+    /// our response), the handler returns only a `()`. As far as the event dispatcher is
+    /// concerned, all the meaningful work will be done as side-effects of the closures you
+    /// register here.
     ///
     /// The types involved here are not stable yet due to ongoing API development.
     ///
@@ -72,20 +150,53 @@ impl ServerBuilder {
     ///
     /// ```Rust
     ///     let github = tide_github::new("my webhook s3ct#t")
-    ///         .on(Event::IssueComment, |payload| {
-    ///             println!("Got payload for repository {}", payload.repository.name)
+    ///         .on(Event::IssueComment, |payload, _ctx| {
+    ///             if let WebhookPayload::IssueComment(payload) = payload {
+    ///                 println!("Got payload for repository {}", payload.repository.name)
+    ///             }
     ///         });
     /// ```
     pub fn on<E: Into<Event>>(
         mut self,
         event: E,
-        handler: impl Fn(Payload)
+        handler: impl Fn(WebhookPayload, Context)
             + Send
             + Sync
             + 'static,
     ) -> Self {
         let event: Event = event.into();
-        self.handlers.insert(event, Arc::new(handler));
+        self.handlers.insert(event, Handler::Blocking(Arc::new(handler)));
+        self
+    }
+
+    /// Registers the given async event handler to be run when the given event is received.
+    ///
+    /// Unlike [`Self::on()`](on@ServerBuilder), the handler is not run via
+    /// `task::spawn_blocking` but is instead driven to completion on the tide/async-std
+    /// executor, which makes it a better fit for handlers that need to do network I/O (for
+    /// example, posting a comment back to Github via `octocrab`).
+    ///
+    /// ## Example
+    ///
+    /// ```Rust
+    ///     let github = tide_github::new("my webhook s3ct#t")
+    ///         .on_async(Event::IssueComment, |payload, _ctx| async move {
+    ///             if let WebhookPayload::IssueComment(payload) = payload {
+    ///                 println!("Got payload for repository {}", payload.repository.name)
+    ///             }
+    ///         });
+    /// ```
+    pub fn on_async<E, F, Fut>(mut self, event: E, handler: F) -> Self
+    where
+        E: Into<Event>,
+        F: Fn(WebhookPayload, Context) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let event: Event = event.into();
+        let handler = Arc::new(move |payload: WebhookPayload, ctx: Context| -> BoxFuture {
+            Box::pin(handler(payload, ctx))
+        });
+        self.handlers.insert(event, Handler::Async(handler));
         self
     }
 
@@ -95,13 +206,42 @@ impl ServerBuilder {
     /// expose the `EventHandlerDispatcher` directly.
     pub fn build(self) -> tide::Server<()> {
         let mut server = tide::new();
-        let dispatcher = Box::new(EventHandlerDispatcher::new(self.handlers));
-        server.with(middleware::WebhookVerification::new(self.webhook_secret));
+        let dispatcher = Box::new(EventHandlerDispatcher::new(
+            self.handlers,
+            self.app_auth,
+            self.delivery_seen,
+        ));
+        server.with(middleware::WebhookVerification::new(self.webhook_secrets));
         server
             .at("/")
             .post(dispatcher as Box<dyn tide::Endpoint<()>>);
         server
     }
+
+    /// Build a [`tide::Server`] that, instead of dispatching to per-event closures registered
+    /// through [`Self::on()`](on@ServerBuilder) or
+    /// [`Self::on_async()`](on_async@ServerBuilder), forwards every verified, parsed event over
+    /// the returned [`async_std::channel::Receiver`].
+    ///
+    /// This decouples the HTTP layer, which only has to push events onto the channel, from event
+    /// handling, whose state (rate limiting, an authenticated client, a database connection, ...)
+    /// can then be owned by a single long-lived consumer task that drains the channel. Any
+    /// handlers registered via `on`/`on_async` are ignored when building this way.
+    pub fn into_stream(
+        self,
+    ) -> (
+        tide::Server<()>,
+        async_std::channel::Receiver<(Event, WebhookPayload, Context)>,
+    ) {
+        let (sender, receiver) = async_std::channel::unbounded();
+        let mut server = tide::new();
+        let dispatcher = Box::new(ChannelDispatcher::new(sender, self.app_auth, self.delivery_seen));
+        server.with(middleware::WebhookVerification::new(self.webhook_secrets));
+        server
+            .at("/")
+            .post(dispatcher as Box<dyn tide::Endpoint<()>>);
+        (server, receiver)
+    }
 }
 
 /// This enum represents the event (and its variants the event type) for which we can receive a
@@ -115,6 +255,30 @@ pub enum Event {
     /// The Github
     /// [`IssueCommentEvent`](https://docs.github.com/en/developers/webhooks-and-events/events/github-event-types#issuecommentevent) event.
     IssueComment,
+    /// The Github
+    /// [`PushEvent`](https://docs.github.com/en/developers/webhooks-and-events/events/github-event-types#pushevent) event.
+    Push,
+    /// The Github
+    /// [`PullRequestEvent`](https://docs.github.com/en/developers/webhooks-and-events/events/github-event-types#pullrequestevent) event.
+    PullRequest,
+    /// The Github
+    /// [`CheckRunEvent`](https://docs.github.com/en/developers/webhooks-and-events/events/github-event-types#checkrunevent) event.
+    CheckRun,
+    /// The Github
+    /// [`CheckSuiteEvent`](https://docs.github.com/en/developers/webhooks-and-events/events/github-event-types#checksuiteevent) event.
+    CheckSuite,
+    /// The Github
+    /// [`IssuesEvent`](https://docs.github.com/en/developers/webhooks-and-events/events/github-event-types#issuesevent) event.
+    Issues,
+    /// The Github
+    /// [`ReleaseEvent`](https://docs.github.com/en/developers/webhooks-and-events/events/github-event-types#releaseevent) event.
+    Release,
+    /// The Github
+    /// [`DeploymentEvent`](https://docs.github.com/en/developers/webhooks-and-events/events/github-event-types#deploymentevent) event.
+    Deployment,
+    /// The Github
+    /// [`PingEvent`](https://docs.github.com/en/developers/webhooks-and-events/events/github-event-types#pingevent) event, sent once when a webhook is first configured.
+    Ping,
 }
 
 use std::fmt;
@@ -122,6 +286,14 @@ impl fmt::Display for Event {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::IssueComment => write!(f, "issue_comment"),
+            Self::Push => write!(f, "push"),
+            Self::PullRequest => write!(f, "pull_request"),
+            Self::CheckRun => write!(f, "check_run"),
+            Self::CheckSuite => write!(f, "check_suite"),
+            Self::Issues => write!(f, "issues"),
+            Self::Release => write!(f, "release"),
+            Self::Deployment => write!(f, "deployment"),
+            Self::Ping => write!(f, "ping"),
         }
     }
 }
@@ -135,6 +307,14 @@ impl ::std::str::FromStr for Event {
         // TODO: Generate this from a derive macro on `Event`
         match event {
             "issue_comment" => Ok(IssueComment),
+            "push" => Ok(Push),
+            "pull_request" => Ok(PullRequest),
+            "check_run" => Ok(CheckRun),
+            "check_suite" => Ok(CheckSuite),
+            "issues" => Ok(Issues),
+            "release" => Ok(Release),
+            "deployment" => Ok(Deployment),
+            "ping" => Ok(Ping),
             event => {
                 log::warn!("Unsupported event: {}", event);
                 Err(EventDispatchError::UnsupportedEvent)
@@ -158,13 +338,139 @@ pub enum EventDispatchError {
     MissingHandlerForEvent(Event),
 }
 
+/// Deserializes the body of `req` into the [`WebhookPayload`] variant matching `event`, alongside
+/// the installation id Github included in the payload (if any).
+///
+/// The event type lives in the `X-Github-Event` header, not in the body, so the caller must
+/// already have parsed it into an [`Event`] before the body can be interpreted correctly.
+async fn parse_webhook_payload(
+    event: Event,
+    req: &mut Request<()>,
+) -> tide::Result<(WebhookPayload, Option<u64>)> {
+    use std::convert::TryInto;
+
+    let body = req.body_bytes().await?;
+
+    let installation_id = serde_json::from_slice::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|value| value.get("installation")?.get("id")?.as_u64());
+
+    let payload = match event {
+        Event::IssueComment => {
+            let payload: Payload = serde_json::from_slice(&body).status(StatusCode::UnprocessableEntity)?;
+            WebhookPayload::IssueComment(payload.try_into().status(StatusCode::UnprocessableEntity)?)
+        }
+        Event::Push => WebhookPayload::Push(
+            serde_json::from_slice(&body).status(StatusCode::UnprocessableEntity)?,
+        ),
+        Event::PullRequest => WebhookPayload::PullRequest(
+            serde_json::from_slice(&body).status(StatusCode::UnprocessableEntity)?,
+        ),
+        Event::CheckRun => WebhookPayload::CheckRun(
+            serde_json::from_slice(&body).status(StatusCode::UnprocessableEntity)?,
+        ),
+        Event::CheckSuite => WebhookPayload::CheckSuite(
+            serde_json::from_slice(&body).status(StatusCode::UnprocessableEntity)?,
+        ),
+        Event::Issues => WebhookPayload::Issues(
+            serde_json::from_slice(&body).status(StatusCode::UnprocessableEntity)?,
+        ),
+        Event::Release => WebhookPayload::Release(
+            serde_json::from_slice(&body).status(StatusCode::UnprocessableEntity)?,
+        ),
+        Event::Deployment => WebhookPayload::Deployment(
+            serde_json::from_slice(&body).status(StatusCode::UnprocessableEntity)?,
+        ),
+        Event::Ping => WebhookPayload::Ping(
+            serde_json::from_slice(&body).status(StatusCode::UnprocessableEntity)?,
+        ),
+    };
+
+    Ok((payload, installation_id))
+}
+
+/// Metadata about an incoming webhook request, read from its headers: the `X-Github-Delivery`
+/// GUID, the raw `X-Github-Event` value and the `X-Github-Hook-ID`. The parsed [`Event`] is not
+/// stored here; [`build_context`] takes it separately.
+struct RequestMetadata {
+    delivery_id: Option<String>,
+    event_name: String,
+    hook_id: Option<u64>,
+}
+
+impl RequestMetadata {
+    fn from_request(event_name: &str, req: &Request<()>) -> Self {
+        RequestMetadata {
+            delivery_id: req.header("X-Github-Delivery").map(|value| value.as_str().to_owned()),
+            event_name: event_name.to_owned(),
+            hook_id: req
+                .header("X-Github-Hook-ID")
+                .and_then(|value| value.as_str().parse().ok()),
+        }
+    }
+}
+
+/// Builds the [`Context`] handed to a handler alongside its [`WebhookPayload`]: the request
+/// metadata, plus an [`octocrab::Octocrab`] client for `installation_id` if app authentication is
+/// configured.
+async fn build_context(
+    app_auth: &Option<Arc<app_auth::AppAuth>>,
+    event: Event,
+    metadata: RequestMetadata,
+    installation_id: Option<u64>,
+) -> Context {
+    let client = match (app_auth, installation_id) {
+        (Some(app_auth), Some(installation_id)) => {
+            match app_auth.client_for_installation(installation_id).await {
+                Ok(client) => Some(client),
+                Err(err) => {
+                    log::warn!("Failed to authenticate installation {}: {}", installation_id, err);
+                    None
+                }
+            }
+        }
+        _ => None,
+    };
+
+    Context {
+        delivery_id: metadata.delivery_id,
+        event_name: metadata.event_name,
+        event,
+        hook_id: metadata.hook_id,
+        client,
+    }
+}
+
+/// Returns `true` if `delivery_id` was already recorded by `delivery_seen` (and the event should
+/// therefore be skipped as a redelivery). Events without a delivery id, or with no dedup store
+/// configured, are never considered duplicates.
+async fn is_duplicate_delivery(
+    delivery_seen: &Option<Arc<dyn DeliverySeen>>,
+    delivery_id: &Option<String>,
+) -> bool {
+    match (delivery_seen, delivery_id) {
+        (Some(delivery_seen), Some(delivery_id)) => delivery_seen.check_and_record(delivery_id).await,
+        _ => false,
+    }
+}
+
 struct EventHandlerDispatcher {
     handlers: HandlerMap,
+    app_auth: Option<Arc<app_auth::AppAuth>>,
+    delivery_seen: Option<Arc<dyn DeliverySeen>>,
 }
 
 impl EventHandlerDispatcher {
-    fn new(handlers: HandlerMap) -> Self {
-        EventHandlerDispatcher { handlers }
+    fn new(
+        handlers: HandlerMap,
+        app_auth: Option<Arc<app_auth::AppAuth>>,
+        delivery_seen: Option<Arc<dyn DeliverySeen>>,
+    ) -> Self {
+        EventHandlerDispatcher {
+            handlers,
+            app_auth,
+            delivery_seen,
+        }
     }
 }
 
@@ -183,16 +489,106 @@ where
             .status(StatusCode::BadRequest)?.as_str();
 
         let event = Event::from_str(event_header).status(StatusCode::NotImplemented)?;
-        let payload: payload::Payload = req.body_json().await?;
+        let metadata = RequestMetadata::from_request(event_header, &req);
+
+        let (payload, installation_id) = parse_webhook_payload(event, &mut req).await?;
         let handler = self
             .handlers
             .get(&event)
-            .ok_or_else(|| { println!("Missing Handler for Event {:?}", event); EventDispatchError::MissingHandlerForEvent(event)})
+            .ok_or_else(|| {
+                log::warn!("Missing handler for Event {:?}", event);
+                EventDispatchError::MissingHandlerForEvent(event)
+            })
             .status(StatusCode::NotImplemented)?;
 
-        let handler = handler.clone();
+        // Only record the delivery as seen once we're actually about to dispatch it to a
+        // handler, so a redelivery caused by a missing handler, a malformed body or a crash
+        // before this point is not permanently (and incorrectly) suppressed.
+        if is_duplicate_delivery(&self.delivery_seen, &metadata.delivery_id).await {
+            log::info!("Skipping already-seen delivery {:?}", metadata.delivery_id);
+            return Ok("".into());
+        }
+
+        let context = build_context(&self.app_auth, event, metadata, installation_id).await;
 
-        task::spawn_blocking(move || {handler(payload)});
+        match handler.clone() {
+            Handler::Blocking(handler) => {
+                task::spawn_blocking(move || handler(payload, context));
+            }
+            Handler::Async(handler) => {
+                task::spawn(handler(payload, context));
+            }
+        }
+
+        Ok("".into())
+    }
+}
+
+impl Clone for Handler {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Blocking(handler) => Self::Blocking(handler.clone()),
+            Self::Async(handler) => Self::Async(handler.clone()),
+        }
+    }
+}
+
+/// Forwards every verified, parsed event over a channel instead of calling a registered closure.
+///
+/// Used by [`ServerBuilder::into_stream()`](into_stream@ServerBuilder) to decouple the HTTP layer
+/// from event handling.
+struct ChannelDispatcher {
+    sender: async_std::channel::Sender<(Event, WebhookPayload, Context)>,
+    app_auth: Option<Arc<app_auth::AppAuth>>,
+    delivery_seen: Option<Arc<dyn DeliverySeen>>,
+}
+
+impl ChannelDispatcher {
+    fn new(
+        sender: async_std::channel::Sender<(Event, WebhookPayload, Context)>,
+        app_auth: Option<Arc<app_auth::AppAuth>>,
+        delivery_seen: Option<Arc<dyn DeliverySeen>>,
+    ) -> Self {
+        ChannelDispatcher {
+            sender,
+            app_auth,
+            delivery_seen,
+        }
+    }
+}
+
+#[async_trait]
+impl tide::Endpoint<()> for ChannelDispatcher
+where
+    ChannelDispatcher: Send + Sync,
+{
+    async fn call(&self, mut req: Request<()>) -> tide::Result {
+        use std::str::FromStr;
+
+        let event_header = req
+            .header("X-Github-Event")
+            .ok_or(EventDispatchError::MissingEventHeader)
+            .status(StatusCode::BadRequest)?
+            .as_str();
+
+        let event = Event::from_str(event_header).status(StatusCode::NotImplemented)?;
+        let metadata = RequestMetadata::from_request(event_header, &req);
+
+        let (payload, installation_id) = parse_webhook_payload(event, &mut req).await?;
+
+        // Only record the delivery as seen once we're actually about to forward it, so a
+        // redelivery caused by a malformed body or a crash before this point is not
+        // permanently (and incorrectly) suppressed.
+        if is_duplicate_delivery(&self.delivery_seen, &metadata.delivery_id).await {
+            log::info!("Skipping already-seen delivery {:?}", metadata.delivery_id);
+            return Ok("".into());
+        }
+
+        let context = build_context(&self.app_auth, event, metadata, installation_id).await;
+
+        if self.sender.send((event, payload, context)).await.is_err() {
+            log::warn!("Dropping event, the receiving end of the event channel was dropped");
+        }
 
         Ok("".into())
     }
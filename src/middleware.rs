@@ -1,10 +1,10 @@
 pub(crate) struct WebhookVerification {
-    secret: String,
+    secrets: Vec<String>,
 }
 
 impl WebhookVerification {
-    pub(crate) fn new(secret: String) -> Self {
-        WebhookVerification { secret }
+    pub(crate) fn new(secrets: Vec<String>) -> Self {
+        WebhookVerification { secrets }
     }
 }
 
@@ -37,20 +37,81 @@ where
                     return Ok(Response::new(StatusCode::BadRequest));
                 }
             };
-            let mut mac: Hmac<Sha256> = Hmac::new_from_slice(&self.secret.as_bytes())?;
             let body = req.body_bytes().await?;
-            mac.update(&body);
-            req.set_body(body);
-            if let Err(err) = mac.verify_slice(&signature) {
-                log::warn!("Failed to verify Github's signature: {}", err);
+            req.set_body(body.clone());
+
+            if !verify_signature(&self.secrets, &signature, &body) {
+                log::warn!("Failed to verify Github's signature: No configured secret matched");
                 return Ok(Response::new(StatusCode::BadRequest));
-            } else {
-                let res = next.run(req).await;
-                Ok(res)
             }
+
+            let res = next.run(req).await;
+            Ok(res)
         } else {
             log::warn!("Event not signed but webhook secret configured, ignoring event");
             return Ok(Response::new(StatusCode::BadRequest));
         }
     }
 }
+
+/// Returns `true` if `signature` is a valid HMAC-SHA256 signature of `body` under *any* of
+/// `secrets`, so that a request is accepted as long as it matches at least one configured secret
+/// (see [`WebhookVerification::new`]).
+fn verify_signature(secrets: &[String], signature: &[u8], body: &[u8]) -> bool {
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    secrets.iter().any(|secret| {
+        let mut mac: Hmac<Sha256> = match Hmac::new_from_slice(secret.as_bytes()) {
+            Ok(mac) => mac,
+            Err(err) => {
+                log::warn!("Failed to initialize HMAC with configured secret: {}", err);
+                return false;
+            }
+        };
+        mac.update(body);
+        mac.verify_slice(signature).is_ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::verify_signature;
+    use hmac::{Hmac, Mac};
+    use sha2::Sha256;
+
+    fn sign(secret: &str, body: &[u8]) -> Vec<u8> {
+        let mut mac: Hmac<Sha256> = Hmac::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    #[test]
+    fn accepts_signature_from_any_configured_secret() {
+        let secrets = vec!["old-secret".to_owned(), "new-secret".to_owned()];
+        let body = b"the request body";
+
+        assert!(verify_signature(&secrets, &sign("old-secret", body), body));
+        assert!(verify_signature(&secrets, &sign("new-secret", body), body));
+    }
+
+    #[test]
+    fn rejects_signature_matching_no_configured_secret() {
+        let secrets = vec!["old-secret".to_owned(), "new-secret".to_owned()];
+        let body = b"the request body";
+
+        assert!(!verify_signature(&secrets, &sign("wrong-secret", body), body));
+    }
+
+    #[test]
+    fn rejects_signature_for_a_different_body() {
+        let secrets = vec!["a-secret".to_owned()];
+        let body = b"the request body";
+
+        assert!(!verify_signature(
+            &secrets,
+            &sign("a-secret", b"a different body"),
+            body
+        ));
+    }
+}
@@ -76,7 +76,13 @@ pub enum Error {
 }
 
 /// Action represents the action the Github webhook is send for.
-#[derive(Deserialize, Debug)]
+///
+/// Which actions are possible depends on the event type the webhook was sent for; this enum
+/// covers the union of actions across all the event types we know about. It is `#[non_exhaustive]`
+/// both because Github keeps adding actions and because we fall back to [`Action::Other`] for any
+/// value we don't explicitly model.
+#[non_exhaustive]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum Action {
     /// The something was created.
@@ -85,4 +91,305 @@ pub enum Action {
     Edited,
     /// The something has been deleted.
     Deleted,
+    /// The something has been opened.
+    Opened,
+    /// The something has been closed.
+    Closed,
+    /// The something has been reopened.
+    Reopened,
+    /// A pull request's branch has been updated with new commits.
+    Synchronize,
+    /// A label has been added.
+    Labeled,
+    /// A label has been removed.
+    Unlabeled,
+    /// A check run/suite has been requested.
+    Requested,
+    /// A check run/suite has been re-requested.
+    Rerequested,
+    /// A check run/suite has completed.
+    Completed,
+    /// A release has been published.
+    Published,
+    /// A release has been unpublished.
+    Unpublished,
+    /// A release has been identified as a prerelease.
+    Prereleased,
+    /// A draft release has been released.
+    Released,
+    /// Any action value we don't explicitly model yet.
+    #[serde(other)]
+    Other,
+}
+
+/// [`WebhookPayload`] is the typed counterpart to the generic [`Payload`]: once the
+/// `X-Github-Event` header has told [`crate::EventHandlerDispatcher`] which [`crate::Event`] we
+/// received, the request body is deserialized straight into the matching variant here instead of
+/// into the mostly-`Option`al [`Payload`].
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum WebhookPayload {
+    /// Payload of an [`crate::Event::IssueComment`] webhook.
+    IssueComment(IssueCommentPayload),
+    /// Payload of an [`crate::Event::Push`] webhook.
+    Push(PushPayload),
+    /// Payload of an [`crate::Event::PullRequest`] webhook.
+    PullRequest(PullRequestPayload),
+    /// Payload of an [`crate::Event::CheckRun`] webhook.
+    CheckRun(CheckRunPayload),
+    /// Payload of an [`crate::Event::CheckSuite`] webhook.
+    CheckSuite(CheckSuitePayload),
+    /// Payload of an [`crate::Event::Issues`] webhook.
+    Issues(IssuesPayload),
+    /// Payload of an [`crate::Event::Release`] webhook.
+    Release(ReleasePayload),
+    /// Payload of an [`crate::Event::Deployment`] webhook.
+    Deployment(DeploymentPayload),
+    /// Payload of an [`crate::Event::Ping`] webhook.
+    Ping(PingPayload),
+}
+
+/// The name/email/username of a commit's author or committer, as reported in a [`PushCommit`].
+#[derive(Deserialize, Debug)]
+pub struct CommitIdentity {
+    /// The name of the author/committer.
+    pub name: String,
+    /// The email address of the author/committer, if known.
+    pub email: Option<String>,
+    /// The Github username of the author/committer, if the commit could be matched to an account.
+    pub username: Option<String>,
+}
+
+/// A single commit included in a [`PushPayload`].
+#[derive(Deserialize, Debug)]
+pub struct PushCommit {
+    /// The SHA of the commit.
+    pub id: String,
+    /// The commit message.
+    pub message: String,
+    /// The ISO 8601 timestamp of the commit.
+    pub timestamp: String,
+    /// The URL to view the commit on Github.
+    pub url: String,
+    /// The author of the commit.
+    pub author: CommitIdentity,
+    /// The committer of the commit.
+    pub committer: CommitIdentity,
+    /// The full paths of the files added by this commit.
+    pub added: Vec<String>,
+    /// The full paths of the files removed by this commit.
+    pub removed: Vec<String>,
+    /// The full paths of the files modified by this commit.
+    pub modified: Vec<String>,
+}
+
+/// [`PushPayload`] represents the payload of a Github `push` webhook.
+///
+/// Unlike most other events, the shape of this payload (a ref being updated, a list of commits)
+/// has little in common with [`Payload`], so it is modeled as its own struct rather than through
+/// a `TryInto` conversion.
+#[derive(Deserialize, Debug)]
+pub struct PushPayload {
+    /// The full git ref that was pushed, e.g. `refs/heads/main`.
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// The SHA of the most recent commit on the ref before the push.
+    pub before: String,
+    /// The SHA of the most recent commit on the ref after the push.
+    pub after: String,
+    /// Whether this push created the ref.
+    pub created: bool,
+    /// Whether this push deleted the ref.
+    pub deleted: bool,
+    /// Whether this push was a force push.
+    pub forced: bool,
+    /// The commits pushed, in chronological order, excluding merge commits.
+    pub commits: Vec<PushCommit>,
+    /// The most recent commit pushed, if any (absent when `deleted` is `true`).
+    pub head_commit: Option<PushCommit>,
+    /// The repository that was pushed to.
+    pub repository: Repository,
+    /// The account that pushed.
+    pub sender: User,
+}
+
+/// A (partial) representation of the `pull_request` object embedded in a [`PullRequestPayload`].
+#[derive(Deserialize, Debug)]
+pub struct PullRequest {
+    /// The id of the pull request.
+    pub id: u64,
+    /// The pull request number.
+    pub number: u64,
+    /// The title of the pull request.
+    pub title: String,
+    /// The description of the pull request.
+    pub body: Option<String>,
+    /// The state of the pull request, e.g. `open` or `closed`.
+    pub state: String,
+    /// The account that opened the pull request.
+    pub user: User,
+    /// Whether the pull request has been merged. Only meaningful once `state` is `closed`.
+    pub merged: Option<bool>,
+}
+
+/// [`PullRequestPayload`] represents the payload of a Github `pull_request` webhook.
+#[derive(Deserialize, Debug)]
+pub struct PullRequestPayload {
+    /// The action that triggered the webhook, e.g. `opened` or `synchronize`.
+    pub action: Action,
+    /// The pull request number.
+    pub number: u64,
+    /// The pull request involved in the action.
+    pub pull_request: PullRequest,
+    /// The repository the pull request belongs to.
+    pub repository: Repository,
+    /// The account that triggered the action.
+    pub sender: User,
+}
+
+/// A (partial) representation of a Github Checks API check run, as embedded in a
+/// [`CheckRunPayload`].
+#[derive(Deserialize, Debug)]
+pub struct CheckRun {
+    /// The id of the check run.
+    pub id: u64,
+    /// The name of the check run.
+    pub name: String,
+    /// The SHA of the commit the check run is for.
+    pub head_sha: String,
+    /// The status of the check run, e.g. `queued`, `in_progress` or `completed`.
+    pub status: String,
+    /// The conclusion of the check run. Only present once `status` is `completed`.
+    pub conclusion: Option<String>,
+}
+
+/// [`CheckRunPayload`] represents the payload of a Github `check_run` webhook.
+#[derive(Deserialize, Debug)]
+pub struct CheckRunPayload {
+    /// The action that triggered the webhook, e.g. `created`, `rerequested` or `completed`.
+    pub action: Action,
+    /// The check run involved in the action.
+    pub check_run: CheckRun,
+    /// The repository the check run belongs to.
+    pub repository: Repository,
+    /// The account that triggered the action.
+    pub sender: User,
+}
+
+/// A (partial) representation of a Github Checks API check suite, as embedded in a
+/// [`CheckSuitePayload`].
+#[derive(Deserialize, Debug)]
+pub struct CheckSuite {
+    /// The id of the check suite.
+    pub id: u64,
+    /// The branch the check suite is running on, if known.
+    pub head_branch: Option<String>,
+    /// The SHA of the commit the check suite is for.
+    pub head_sha: String,
+    /// The status of the check suite, e.g. `queued`, `in_progress` or `completed`.
+    pub status: String,
+    /// The conclusion of the check suite. Only present once `status` is `completed`.
+    pub conclusion: Option<String>,
+}
+
+/// [`CheckSuitePayload`] represents the payload of a Github `check_suite` webhook.
+#[derive(Deserialize, Debug)]
+pub struct CheckSuitePayload {
+    /// The action that triggered the webhook, e.g. `requested`, `rerequested` or `completed`.
+    pub action: Action,
+    /// The check suite involved in the action.
+    pub check_suite: CheckSuite,
+    /// The repository the check suite belongs to.
+    pub repository: Repository,
+    /// The account that triggered the action.
+    pub sender: User,
+}
+
+/// [`IssuesPayload`] represents the payload of a Github `issues` webhook.
+///
+/// This is distinct from [`IssueCommentPayload`], which is for comments posted on an issue, not
+/// the issue itself being opened/closed/labeled/etc.
+#[derive(Deserialize, Debug)]
+pub struct IssuesPayload {
+    /// The action that triggered the webhook, e.g. `opened`, `closed` or `labeled`.
+    pub action: Action,
+    /// The issue involved in the action.
+    pub issue: Issue,
+    /// The repository the issue belongs to.
+    pub repository: Repository,
+    /// The account that triggered the action.
+    pub sender: User,
+}
+
+/// A (partial) representation of a Github release, as embedded in a [`ReleasePayload`].
+#[derive(Deserialize, Debug)]
+pub struct Release {
+    /// The id of the release.
+    pub id: u64,
+    /// The git tag the release is for.
+    pub tag_name: String,
+    /// The name of the release, if one was given.
+    pub name: Option<String>,
+    /// Whether the release is a draft.
+    pub draft: bool,
+    /// Whether the release is a prerelease.
+    pub prerelease: bool,
+    /// The release notes.
+    pub body: Option<String>,
+}
+
+/// [`ReleasePayload`] represents the payload of a Github `release` webhook.
+#[derive(Deserialize, Debug)]
+pub struct ReleasePayload {
+    /// The action that triggered the webhook, e.g. `published` or `prereleased`.
+    pub action: Action,
+    /// The release involved in the action.
+    pub release: Release,
+    /// The repository the release belongs to.
+    pub repository: Repository,
+    /// The account that triggered the action.
+    pub sender: User,
+}
+
+/// A (partial) representation of a Github deployment, as embedded in a [`DeploymentPayload`].
+#[derive(Deserialize, Debug)]
+pub struct Deployment {
+    /// The id of the deployment.
+    pub id: u64,
+    /// The git ref that was deployed.
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    /// The deployment task, e.g. `deploy`.
+    pub task: String,
+    /// The name of the target environment, e.g. `production`.
+    pub environment: String,
+    /// The description given when creating the deployment.
+    pub description: Option<String>,
+}
+
+/// [`DeploymentPayload`] represents the payload of a Github `deployment` webhook.
+#[derive(Deserialize, Debug)]
+pub struct DeploymentPayload {
+    /// The action that triggered the webhook.
+    pub action: Action,
+    /// The deployment involved in the action.
+    pub deployment: Deployment,
+    /// The repository the deployment belongs to.
+    pub repository: Repository,
+    /// The account that triggered the action.
+    pub sender: User,
+}
+
+/// [`PingPayload`] represents the payload of a Github `ping` webhook, sent once when a webhook is
+/// first configured.
+#[derive(Deserialize, Debug)]
+pub struct PingPayload {
+    /// A random string Github sends to let us confirm the webhook is wired up correctly.
+    pub zen: String,
+    /// The id of the webhook that was configured.
+    pub hook_id: u64,
+    /// The repository the webhook was configured on. Absent for organization- or app-level hooks.
+    pub repository: Option<Repository>,
+    /// The account that configured the webhook.
+    pub sender: Option<User>,
 }